@@ -11,12 +11,15 @@ use lv2_core::feature::Feature;
 use lv2_core::prelude::*;
 use lv2_sys as sys;
 use std::ffi::*;
+use std::fs::File;
+use std::io::{self, Read, Write};
 use std::iter::once;
 use std::marker::PhantomData;
 use std::os::raw::c_char;
 use std::path::*;
 use std::rc::Rc;
 use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
 use urid::*;
 
 /// An error that may occur when handling paths.
@@ -31,6 +34,16 @@ pub enum PathError {
     /// The host does not comply to the specification.
     HostError,
     FeatureMissing,
+    /// Reading or writing a managed file failed.
+    Io(io::Error),
+    /// The abstract path, once resolved to an absolute path, escapes the plugin's namespace root.
+    PathEscapesNamespace,
+}
+
+impl From<io::Error> for PathError {
+    fn from(error: io::Error) -> Self {
+        PathError::Io(error)
+    }
 }
 
 /// A host feature to make absolute paths.
@@ -85,6 +98,44 @@ impl<'a> MakePath<'a> {
     }
 }
 
+/// Rejects abstract paths that try to step outside of the plugin's namespace, e.g. a path
+/// restored from an untrusted state bundle containing something like `../../etc/foo`.
+fn reject_escaping_components(path: &str) -> Result<(), PathError> {
+    for component in Path::new(path).components() {
+        match component {
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(PathError::PathEscapesNamespace)
+            }
+            Component::CurDir | Component::Normal(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies that `path` is confined to `root`, without requiring `path` itself to exist yet.
+///
+/// `path` may be about to be created (e.g. a state file being restored), so only its parent
+/// directory — which the host is expected to have already created — is canonicalized and
+/// checked. If `path` does happen to exist already, its own canonical form (following any
+/// symlink it might be) is checked too.
+fn verify_within_namespace(path: &Path, root: &Path) -> Result<(), PathError> {
+    let parent = path.parent().unwrap_or(path);
+    let canonical_parent = parent.canonicalize()?;
+
+    if !canonical_parent.starts_with(root) {
+        return Err(PathError::PathEscapesNamespace);
+    }
+
+    if path.exists() {
+        if !path.canonicalize()?.starts_with(root) {
+            return Err(PathError::PathEscapesNamespace);
+        }
+    }
+
+    Ok(())
+}
+
 /// A host feature to save and restore files.
 pub struct MapPath<'a> {
     handle: sys::LV2_State_Map_Path_Handle,
@@ -144,6 +195,8 @@ impl<'a> MapPath<'a> {
     }
 
     fn abstract_to_absolute_path(&mut self, path: &str) -> Result<&'a Path, PathError> {
+        reject_escaping_components(path)?;
+
         let path: Vec<c_char> = path.bytes().chain(once(0)).map(|b| b as c_char).collect();
 
         let path = unsafe { (self.absolute_path)(self.handle, path.as_ptr()) };
@@ -236,10 +289,66 @@ impl<'a> Drop for ManagedStr<'a> {
     }
 }
 
+/// Whether a [`ManagedMmap`] is backed by a genuine memory mapping or by a buffered, copied-in read.
+///
+/// Memory-mapping is unreliable over networked filesystems, so [`PathManager::map_readonly`] falls back to a plain read in that case; callers that care (e.g. to log or adapt real-time behavior) can inspect this via [`ManagedMmap::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MmapKind {
+    /// The file is truly memory-mapped.
+    Mapped,
+    /// The file was read into an owned buffer, e.g. because it lives on an unreliable networked filesystem.
+    Buffered,
+}
+
+// This module's mmap support needs `memmap2` (for the mapping itself) and `libc` (for the
+// `statfs` filesystem-kind check below) declared as dependencies in this crate's `Cargo.toml`.
+enum MmapBacking {
+    Mapped(memmap2::Mmap),
+    Buffered(Vec<u8>),
+}
+
+/// A memory-mapped (or, on unreliable filesystems, buffered) read-only view of a restored state file.
+///
+/// Obtained through [`PathManager::map_readonly`]. Dropping it unmaps the underlying file, if any, and frees the abstract path through [`FreePath`], mirroring [`ManagedPath`] and [`ManagedStr`].
+pub struct ManagedMmap<'a> {
+    backing: MmapBacking,
+    kind: MmapKind,
+    path: &'a Path,
+    free_path: FreePath<'a>,
+}
+
+impl<'a> ManagedMmap<'a> {
+    /// Whether this handle is a true zero-copy memory mapping or a buffered fallback.
+    pub fn kind(&self) -> MmapKind {
+        self.kind
+    }
+}
+
+impl<'a> std::ops::Deref for ManagedMmap<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.backing {
+            MmapBacking::Mapped(mmap) => &mmap[..],
+            MmapBacking::Buffered(buffer) => &buffer[..],
+        }
+    }
+}
+
+impl<'a> Drop for ManagedMmap<'a> {
+    fn drop(&mut self) {
+        self.free_path.free_path(self.path.to_str().unwrap())
+    }
+}
+
 pub struct PathManager<'a> {
     make: MakePath<'a>,
     map: Option<MapPath<'a>>,
     free: FreePath<'a>,
+    /// Abstract/absolute path pairs that have been mapped via [`absolute_to_abstract_path`](Self::absolute_to_abstract_path) so far, i.e. the files that make up this instance's persistent state.
+    tracked: Vec<(String, PathBuf)>,
+    /// Canonicalized namespace root, lazily resolved through `make` and cached since it cannot change over the lifetime of a plugin instance.
+    namespace_root: Option<PathBuf>,
 }
 
 impl<'a> PathManager<'a> {
@@ -248,6 +357,8 @@ impl<'a> PathManager<'a> {
             make,
             map: None,
             free,
+            tracked: Vec::new(),
+            namespace_root: None,
         }
     }
 
@@ -256,9 +367,27 @@ impl<'a> PathManager<'a> {
             make,
             map: Some(map),
             free,
+            tracked: Vec::new(),
+            namespace_root: None,
         }
     }
 
+    /// Resolves and caches the canonical namespace root the host confines this plugin instance to.
+    fn namespace_root(&mut self) -> Result<PathBuf, PathError> {
+        if let Some(root) = &self.namespace_root {
+            return Ok(root.clone());
+        }
+
+        let probe = ManagedPath {
+            path: self.make.relative_to_absolute_path(Path::new("."))?,
+            free_path: self.free.clone(),
+        };
+        let root = probe.canonicalize()?;
+
+        self.namespace_root = Some(root.clone());
+        Ok(root)
+    }
+
     pub fn relative_to_absolute_path(
         &mut self,
         relative_path: &Path,
@@ -272,36 +401,737 @@ impl<'a> PathManager<'a> {
     }
 
     pub fn absolute_to_abstract_path(&mut self, path: &Path) -> Result<ManagedStr<'a>, PathError> {
-        self.map
+        let abstract_path = self
+            .map
             .as_mut()
             .ok_or(PathError::FeatureMissing)
-            .and_then(|map| map.absolute_to_abstract_path(path))
-            .map(|str| ManagedStr {
-                str,
-                free_path: self.free.clone(),
-            })
+            .and_then(|map| map.absolute_to_abstract_path(path))?;
+
+        self.tracked
+            .push((abstract_path.to_owned(), path.to_owned()));
+
+        Ok(ManagedStr {
+            str: abstract_path,
+            free_path: self.free.clone(),
+        })
     }
 
     pub fn abstract_to_absolute_path(&mut self, path: &str) -> Result<ManagedPath<'a>, PathError> {
-        self.map
+        let managed = self
+            .map
             .as_mut()
             .ok_or(PathError::FeatureMissing)
             .and_then(|map| map.abstract_to_absolute_path(path))
             .map(|path| ManagedPath {
                 path,
                 free_path: self.free.clone(),
-            })
+            })?;
+
+        let root = self.namespace_root()?;
+        verify_within_namespace(&managed, &root)?;
+
+        Ok(managed)
+    }
+
+    /// Write every file mapped so far via [`absolute_to_abstract_path`](Self::absolute_to_abstract_path) into a single uncompressed tar stream, keyed by its abstract path.
+    ///
+    /// The resulting bundle is host-independent and can be restored on another machine or plugin instance with [`import_bundle`](Self::import_bundle). Modification time and unix permission bits are preserved; abstract paths longer than the 100 bytes of the classic tar `name` field are stored using a PAX extended header record instead of being truncated.
+    pub fn export_bundle<W: Write>(&mut self, mut writer: W) -> Result<(), PathError> {
+        for (abstract_path, absolute_path) in &self.tracked {
+            let mut file = File::open(absolute_path)?;
+            let metadata = file.metadata()?;
+
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+
+            let mode = unix_mode(&metadata);
+
+            tar::write_entry(&mut writer, abstract_path, &mut file, metadata.len(), mtime, mode)?;
+        }
+
+        tar::write_end(&mut writer)
+    }
+
+    /// Memory-maps a restored state file for zero-copy, real-time-friendly reads instead of copying it into a `Vec` up front.
+    ///
+    /// `path` is consumed (deliberately, not borrowed): once mapped, the returned [`ManagedMmap`] takes over responsibility for freeing the abstract path, so it is fine to map a path and never touch the original [`ManagedPath`] again. Taking `path` by reference instead would make both `ManagedMmap::drop` and the borrowed `ManagedPath`'s own `drop` free the same abstract path, a double free. Because `mmap` over a network filesystem is unreliable, the backing filesystem is detected first; NFS and SMB mounts (and, on non-Linux platforms, everything) fall back to a buffered read instead of an actual mapping. Use [`ManagedMmap::kind`] to inspect which one happened.
+    pub fn map_readonly(&self, path: ManagedPath<'a>) -> Result<ManagedMmap<'a>, PathError> {
+        // Keep the original host-allocated pointer, not a Rust-owned copy: `ManagedMmap::drop`
+        // must hand the exact pointer the host returned back to `FreePath`.
+        let host_path = path.path;
+        let free_path = path.free_path.clone();
+        // `ManagedMmap` now owns freeing this abstract path; don't let `path`'s `Drop` free it too.
+        std::mem::forget(path);
+
+        let mut kind = detect_mmap_kind(host_path);
+
+        let backing = match kind {
+            MmapKind::Mapped => {
+                let file = File::open(host_path)?;
+                if file.metadata()?.len() == 0 {
+                    // `memmap2::Mmap::map` errors on a zero-length file on common platforms.
+                    kind = MmapKind::Buffered;
+                    MmapBacking::Buffered(Vec::new())
+                } else {
+                    MmapBacking::Mapped(unsafe { memmap2::Mmap::map(&file)? })
+                }
+            }
+            MmapKind::Buffered => MmapBacking::Buffered(std::fs::read(host_path)?),
+        };
+
+        Ok(ManagedMmap {
+            backing,
+            kind,
+            path: host_path,
+            free_path,
+        })
+    }
+
+    /// Writes `contents` into `relative_path` without ever leaving a half-written file behind if the host or process dies mid-save.
+    ///
+    /// The data is first written to a sibling temporary path obtained via `MakePath`, `fsync`ed, then atomically renamed over the final target (rename is atomic within a filesystem). The temporary path is freed through [`FreePath`] once the rename has committed. Returns a [`ManagedPath`] pointing at the final, committed file.
+    pub fn atomic_write(
+        &mut self,
+        relative_path: &Path,
+        contents: impl FnOnce(&mut File) -> io::Result<()>,
+    ) -> Result<ManagedPath<'a>, PathError> {
+        let temp_relative = sibling_temp_path(relative_path)?;
+        let temp = self.relative_to_absolute_path(&temp_relative)?;
+
+        if let Err(error) = write_and_sync(&temp, contents) {
+            let _ = std::fs::remove_file(&*temp);
+            return Err(error.into());
+        }
+
+        let target = match self.relative_to_absolute_path(relative_path) {
+            Ok(target) => target,
+            Err(error) => {
+                let _ = std::fs::remove_file(&*temp);
+                return Err(error);
+            }
+        };
+
+        if let Err(error) = std::fs::rename(&*temp, &*target) {
+            let _ = std::fs::remove_file(&*temp);
+            return Err(error.into());
+        }
+        drop(temp); // frees the temporary abstract path through `FreePath`
+
+        Ok(target)
+    }
+
+    /// Restore every file contained in a bundle previously written by [`export_bundle`](Self::export_bundle).
+    ///
+    /// Each entry is materialized at the abstract path stored in the tar stream by resolving it through [`abstract_to_absolute_path`](Self::abstract_to_absolute_path), then its modification time and unix permission bits are reapplied. The returned [`ManagedPath`]s let the caller control how long the restored files stay registered with the host.
+    pub fn import_bundle<R: Read>(&mut self, mut reader: R) -> Result<Vec<ManagedPath<'a>>, PathError> {
+        let mut restored = Vec::new();
+
+        while let Some(entry) = tar::read_entry(&mut reader)? {
+            let managed = self.abstract_to_absolute_path(&entry.path)?;
+
+            {
+                let mut file = File::create(&*managed)?;
+                file.write_all(&entry.contents)?;
+                file.sync_all()?;
+            }
+
+            // Apply mtime before mode: a read-only mode (e.g. `0o444` on a shipped sample/IR
+            // asset) would otherwise strip the owner-write bit `File::create` left in place,
+            // and `set_mtime`'s `open(.write(true))` would then fail with `EACCES`.
+            set_mtime(&managed, entry.mtime)?;
+            set_unix_mode(&managed, entry.mode)?;
+
+            restored.push(managed);
+        }
+
+        Ok(restored)
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_metadata: &std::fs::Metadata) -> u32 {
+    0o644
+}
+
+#[cfg(unix)]
+fn set_unix_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_unix_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Detects whether `path` can be safely memory-mapped, or whether it lives on a networked
+/// filesystem (NFS, SMB) where mmap is unreliable and a buffered read should be used instead.
+#[cfg(target_os = "linux")]
+fn detect_mmap_kind(path: &Path) -> MmapKind {
+    use std::os::unix::ffi::OsStrExt;
+
+    const NFS_SUPER_MAGIC: i64 = 0x6969;
+    const SMB_SUPER_MAGIC: i64 = 0x517B;
+
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(_) => return MmapKind::Buffered,
+    };
+
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return MmapKind::Buffered;
+    }
+
+    match stat.f_type as i64 {
+        NFS_SUPER_MAGIC | SMB_SUPER_MAGIC => MmapKind::Buffered,
+        _ => MmapKind::Mapped,
+    }
+}
+
+/// Outside Linux no `statfs` check is made, so there is no way here to detect a networked mount;
+/// always take the safe buffered-read path.
+#[cfg(not(target_os = "linux"))]
+fn detect_mmap_kind(_path: &Path) -> MmapKind {
+    MmapKind::Buffered
+}
+
+/// Writes and `fsync`s the temporary file used by [`PathManager::atomic_write`]. Kept separate so the caller can remove the temporary file on any failure from this point on.
+fn write_and_sync(temp: &Path, contents: impl FnOnce(&mut File) -> io::Result<()>) -> io::Result<()> {
+    let mut file = File::create(temp)?;
+    contents(&mut file)?;
+    file.sync_all()
+}
+
+/// Builds a sibling path for `relative_path` with a random suffix appended to its file name, e.g. `preset.ttl` becomes `preset.ttl.a1b2c3d4.tmp`.
+fn sibling_temp_path(relative_path: &Path) -> Result<PathBuf, PathError> {
+    let file_name = relative_path.file_name().ok_or(PathError::PathNotRelative)?;
+
+    let mut temp_name = file_name.to_owned();
+    temp_name.push(format!(".{}.tmp", random_suffix()));
+
+    Ok(relative_path.with_file_name(temp_name))
+}
+
+/// A process-unique, non-cryptographic suffix, good enough to avoid colliding with a concurrent
+/// save of the same file.
+fn random_suffix() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{:x}{:x}", nanos, count)
+}
+
+fn set_mtime(path: &Path, mtime: u64) -> io::Result<()> {
+    let mtime = UNIX_EPOCH + std::time::Duration::from_secs(mtime);
+    let file = std::fs::OpenOptions::new().write(true).open(path)?;
+    file.set_modified(mtime)
+}
+
+/// A minimal writer/reader for the subset of the ustar/PAX tar format needed by
+/// [`PathManager::export_bundle`](super::PathManager::export_bundle) and
+/// [`PathManager::import_bundle`](super::PathManager::import_bundle).
+mod tar {
+    use super::*;
+
+    const BLOCK_SIZE: usize = 512;
+
+    pub struct Entry {
+        pub path: String,
+        pub contents: Vec<u8>,
+        pub mtime: u64,
+        pub mode: u32,
+    }
+
+    /// Writes a single tar entry, preceded by a PAX extended header record if `path` does not
+    /// fit in the 100-byte ustar `name` field.
+    pub fn write_entry<W: Write>(
+        writer: &mut W,
+        path: &str,
+        contents: &mut impl Read,
+        size: u64,
+        mtime: u64,
+        mode: u32,
+    ) -> io::Result<()> {
+        let path_bytes = path.as_bytes();
+
+        if path_bytes.len() > 100 {
+            let record = pax_path_record(path);
+            let mut header = [0u8; BLOCK_SIZE];
+            fill_header(
+                &mut header,
+                truncate_name(path),
+                record.len() as u64,
+                mtime,
+                0o644,
+                b'x',
+            );
+            writer.write_all(&header)?;
+            writer.write_all(&record)?;
+            writer.write_all(&padding(record.len()))?;
+        }
+
+        let mut header = [0u8; BLOCK_SIZE];
+        fill_header(&mut header, truncate_name(path), size, mtime, mode, b'0');
+        writer.write_all(&header)?;
+
+        let mut buf = [0u8; 8192];
+        let mut remaining = size;
+        while remaining > 0 {
+            let to_read = remaining.min(buf.len() as u64) as usize;
+            contents.read_exact(&mut buf[..to_read])?;
+            writer.write_all(&buf[..to_read])?;
+            remaining -= to_read as u64;
+        }
+        writer.write_all(&padding(size as usize))?;
+
+        Ok(())
+    }
+
+    /// Writes the two all-zero blocks that mark the end of a tar archive.
+    pub fn write_end<W: Write>(writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[0u8; BLOCK_SIZE * 2])
+    }
+
+    /// Reads the next entry from a tar stream, applying a preceding PAX extended header if
+    /// present. Returns `None` once the end-of-archive marker is reached.
+    pub fn read_entry<R: Read>(reader: &mut R) -> io::Result<Option<Entry>> {
+        let mut header = [0u8; BLOCK_SIZE];
+        let mut pax_path = None;
+
+        loop {
+            if !read_block(reader, &mut header)? {
+                return Ok(None);
+            }
+            if header.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+
+            let size = parse_octal(&header[124..136]);
+            let typeflag = header[156];
+
+            if typeflag == b'x' {
+                let mut data = vec![0u8; size as usize];
+                reader.read_exact(&mut data)?;
+                skip_padding(reader, size as usize)?;
+                pax_path = parse_pax_path(&data);
+                continue;
+            }
+
+            let mtime = parse_octal(&header[136..148]);
+            let mode = parse_octal(&header[100..108]) as u32;
+            let name = pax_path.unwrap_or_else(|| parse_name(&header));
+
+            let mut contents = vec![0u8; size as usize];
+            reader.read_exact(&mut contents)?;
+            skip_padding(reader, size as usize)?;
+
+            return Ok(Some(Entry {
+                path: name,
+                contents,
+                mtime,
+                mode,
+            }));
+        }
+    }
+
+    fn read_block<R: Read>(reader: &mut R, block: &mut [u8; BLOCK_SIZE]) -> io::Result<bool> {
+        match reader.read_exact(block) {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn skip_padding<R: Read>(reader: &mut R, size: usize) -> io::Result<()> {
+        let pad = padding(size);
+        if !pad.is_empty() {
+            let mut discard = vec![0u8; pad.len()];
+            reader.read_exact(&mut discard)?;
+        }
+        Ok(())
+    }
+
+    fn padding(size: usize) -> Vec<u8> {
+        let remainder = size % BLOCK_SIZE;
+        if remainder == 0 {
+            Vec::new()
+        } else {
+            vec![0u8; BLOCK_SIZE - remainder]
+        }
+    }
+
+    fn truncate_name(path: &str) -> &str {
+        if path.len() > 100 {
+            &path[..100]
+        } else {
+            path
+        }
+    }
+
+    fn fill_header(header: &mut [u8; BLOCK_SIZE], name: &str, size: u64, mtime: u64, mode: u32, typeflag: u8) {
+        header[0..name.len()].copy_from_slice(name.as_bytes());
+        header[100..108].copy_from_slice(&octal_field(mode as u64, 8));
+        header[108..116].copy_from_slice(&octal_field(0, 8)); // uid
+        header[116..124].copy_from_slice(&octal_field(0, 8)); // gid
+        header[124..136].copy_from_slice(&octal_field(size, 12));
+        header[136..148].copy_from_slice(&octal_field(mtime, 12));
+        header[148..156].copy_from_slice(b"        "); // chksum, filled in below
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..155].copy_from_slice(&octal_field(checksum as u64, 7));
+        header[155] = b' ';
+    }
+
+    fn octal_field(value: u64, width: usize) -> Vec<u8> {
+        let digits = format!("{:o}", value);
+        let digits = if digits.len() > width - 1 {
+            digits[digits.len() - (width - 1)..].to_string()
+        } else {
+            digits
+        };
+
+        let mut field = vec![b'0'; width];
+        let start = width - 1 - digits.len();
+        field[start..width - 1].copy_from_slice(digits.as_bytes());
+        field[width - 1] = 0;
+        field
+    }
+
+    fn parse_octal(field: &[u8]) -> u64 {
+        let text = field
+            .iter()
+            .take_while(|&&b| b != 0 && b != b' ')
+            .collect::<Vec<_>>();
+        let text: String = text.iter().map(|&&b| b as char).collect();
+        u64::from_str_radix(&text, 8).unwrap_or(0)
+    }
+
+    fn parse_name(header: &[u8; BLOCK_SIZE]) -> String {
+        let name = &header[0..100];
+        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        String::from_utf8_lossy(&name[..end]).into_owned()
+    }
+
+    /// Builds the self-describing `"<len> path=<path>\n"` PAX record, accounting for the fact
+    /// that the length prefix includes its own digit count.
+    fn pax_path_record(path: &str) -> Vec<u8> {
+        let suffix = format!(" path={}\n", path);
+        let mut len = suffix.len() + 1;
+        loop {
+            let candidate = len.to_string().len() + suffix.len();
+            if candidate == len {
+                break;
+            }
+            len = candidate;
+        }
+        format!("{}{}", len, suffix).into_bytes()
+    }
+
+    fn parse_pax_path(data: &[u8]) -> Option<String> {
+        let text = String::from_utf8_lossy(data);
+        for line in text.split_terminator('\n') {
+            let rest = line.splitn(2, ' ').nth(1)?;
+            if let Some(value) = rest.strip_prefix("path=") {
+                return Some(value.to_owned());
+            }
+        }
+        None
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use std::os::raw::c_char;
 
+    /// Creates a fresh, empty temporary directory to act as a fake plugin namespace root.
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rust-lv2-path-test-{}-{}", name, random_suffix()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A fake `MakePath`: joins the namespace root with the relative path, creating any missing
+    /// parent directories, the way a real host would.
     unsafe extern "C" fn make_path_impl(
         handle: sys::LV2_State_Make_Path_Handle,
         relative_path: *const c_char,
     ) -> *mut c_char {
-        std::ptr::null_mut()
+        let root = &*(handle as *const PathBuf);
+        let relative = CStr::from_ptr(relative_path).to_str().unwrap();
+        let absolute = root.join(relative);
+        std::fs::create_dir_all(absolute.parent().unwrap()).unwrap();
+        CString::new(absolute.to_str().unwrap()).unwrap().into_raw()
+    }
+
+    /// A fake `MapPath::abstract_path`: the abstract path is just the namespace-relative path.
+    unsafe extern "C" fn absolute_to_abstract_impl(
+        handle: sys::LV2_State_Map_Path_Handle,
+        absolute_path: *const c_char,
+    ) -> *mut c_char {
+        let root = &*(handle as *const PathBuf);
+        let absolute = CStr::from_ptr(absolute_path).to_str().unwrap();
+        let relative = Path::new(absolute).strip_prefix(root).unwrap();
+        CString::new(relative.to_str().unwrap()).unwrap().into_raw()
+    }
+
+    /// A fake `MapPath::absolute_path`: joins the namespace root back onto the abstract path,
+    /// creating any missing parent directories, the way a real host's state restore would.
+    unsafe extern "C" fn abstract_to_absolute_impl(
+        handle: sys::LV2_State_Map_Path_Handle,
+        abstract_path: *const c_char,
+    ) -> *mut c_char {
+        let root = &*(handle as *const PathBuf);
+        let relative = CStr::from_ptr(abstract_path).to_str().unwrap();
+        let absolute = root.join(relative);
+        std::fs::create_dir_all(absolute.parent().unwrap()).unwrap();
+        CString::new(absolute.to_str().unwrap()).unwrap().into_raw()
+    }
+
+    /// A misbehaving `MapPath::absolute_path` that ignores the namespace root entirely, standing
+    /// in for a host bug or a corrupted abstract path that resolves outside the namespace.
+    unsafe extern "C" fn abstract_to_absolute_escaping_impl(
+        _handle: sys::LV2_State_Map_Path_Handle,
+        _abstract_path: *const c_char,
+    ) -> *mut c_char {
+        CString::new("/etc").unwrap().into_raw()
+    }
+
+    unsafe extern "C" fn free_path_impl(_handle: sys::LV2_State_Free_Path_Handle, path: *mut c_char) {
+        drop(CString::from_raw(path));
+    }
+
+    fn leak_root(root: &PathBuf) -> *mut PathBuf {
+        Box::into_raw(Box::new(root.clone()))
+    }
+
+    fn path_manager(root: &PathBuf) -> PathManager<'static> {
+        let handle = leak_root(root);
+        let make = MakePath {
+            handle: handle as _,
+            function: make_path_impl,
+            lifetime: PhantomData,
+        };
+        let map = MapPath {
+            handle: handle as _,
+            abstract_path: absolute_to_abstract_impl,
+            absolute_path: abstract_to_absolute_impl,
+            lifetime: PhantomData,
+        };
+        let free = FreePath {
+            internal: Rc::new(Mutex::new(FreePathImpl {
+                handle: handle as _,
+                free_path: free_path_impl,
+                lifetime: PhantomData,
+            })),
+        };
+        PathManager::new_with_map(make, map, free)
+    }
+
+    #[test]
+    fn abstract_to_absolute_path_rejects_parent_dir_components() {
+        let root = temp_root("escape-string");
+        let mut manager = path_manager(&root);
+
+        let error = manager
+            .abstract_to_absolute_path("../../etc/passwd")
+            .unwrap_err();
+
+        assert!(matches!(error, PathError::PathEscapesNamespace));
+    }
+
+    #[test]
+    fn abstract_to_absolute_path_rejects_host_result_outside_namespace() {
+        let root = temp_root("escape-host");
+        let handle = leak_root(&root);
+        let make = MakePath {
+            handle: handle as _,
+            function: make_path_impl,
+            lifetime: PhantomData,
+        };
+        let map = MapPath {
+            handle: handle as _,
+            abstract_path: absolute_to_abstract_impl,
+            absolute_path: abstract_to_absolute_escaping_impl,
+            lifetime: PhantomData,
+        };
+        let free = FreePath {
+            internal: Rc::new(Mutex::new(FreePathImpl {
+                handle: handle as _,
+                free_path: free_path_impl,
+                lifetime: PhantomData,
+            })),
+        };
+        let mut manager = PathManager::new_with_map(make, map, free);
+
+        let error = manager.abstract_to_absolute_path("safe.ttl").unwrap_err();
+
+        assert!(matches!(error, PathError::PathEscapesNamespace));
+    }
+
+    #[test]
+    fn abstract_to_absolute_path_allows_not_yet_existing_files() {
+        let root = temp_root("create-new");
+        let mut manager = path_manager(&root);
+
+        // The file does not exist on disk yet; resolving its abstract path (as `import_bundle`
+        // does before writing it) must still succeed since only the parent directory, not the
+        // file itself, is required to exist.
+        let managed = manager.abstract_to_absolute_path("restored/state.bin").unwrap();
+
+        assert!(!managed.exists());
+        assert!(managed.parent().unwrap().exists());
+    }
+
+    #[test]
+    fn map_readonly_reads_mapped_file_contents() {
+        let root = temp_root("mmap");
+        let mut manager = path_manager(&root);
+
+        let managed = manager
+            .relative_to_absolute_path(Path::new("asset.bin"))
+            .unwrap();
+        std::fs::write(&*managed, b"zero-copy").unwrap();
+
+        let mapped = manager.map_readonly(managed).unwrap();
+
+        assert_eq!(&mapped[..], b"zero-copy");
+        assert_eq!(mapped.kind(), MmapKind::Mapped);
+    }
+
+    #[test]
+    fn map_readonly_falls_back_to_buffered_for_empty_file() {
+        let root = temp_root("mmap-empty");
+        let mut manager = path_manager(&root);
+
+        let managed = manager
+            .relative_to_absolute_path(Path::new("empty.bin"))
+            .unwrap();
+        std::fs::write(&*managed, b"").unwrap();
+
+        let mapped = manager.map_readonly(managed).unwrap();
+
+        assert_eq!(&mapped[..], b"");
+        assert_eq!(mapped.kind(), MmapKind::Buffered);
+    }
+
+    #[test]
+    fn atomic_write_commits_file_and_leaves_no_temp_behind() {
+        let root = temp_root("atomic-commit");
+        let mut manager = path_manager(&root);
+
+        let managed = manager
+            .atomic_write(Path::new("state.bin"), |file| file.write_all(b"committed"))
+            .unwrap();
+
+        assert_eq!(std::fs::read(&*managed).unwrap(), b"committed");
+        assert!(std::fs::read_dir(&root)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .all(|entry| !entry.file_name().to_string_lossy().contains(".tmp")));
+    }
+
+    #[test]
+    fn atomic_write_cleans_up_temp_file_on_write_failure() {
+        let root = temp_root("atomic-fail");
+        let mut manager = path_manager(&root);
+
+        let error = manager
+            .atomic_write(Path::new("state.bin"), |_file| {
+                Err(io::Error::new(io::ErrorKind::Other, "simulated failure"))
+            })
+            .unwrap_err();
+
+        assert!(matches!(error, PathError::Io(_)));
+        assert!(std::fs::read_dir(&root).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn export_then_import_bundle_round_trips_files() {
+        let root = temp_root("bundle-export");
+        let mut manager = path_manager(&root);
+
+        let short = manager
+            .relative_to_absolute_path(Path::new("preset.ttl"))
+            .unwrap();
+        std::fs::write(&*short, b"hello world").unwrap();
+        manager.absolute_to_abstract_path(&short).unwrap();
+
+        // Long enough to force the PAX extended header branch instead of the 100-byte ustar name field.
+        let long_relative = Path::new(
+            "deeply/nested/directory/structure/that/is/long/enough/to/force/a/pax/extended/header/record/sample.wav",
+        );
+        let long = manager.relative_to_absolute_path(long_relative).unwrap();
+        std::fs::write(&*long, b"binary data").unwrap();
+        manager.absolute_to_abstract_path(&long).unwrap();
+
+        let mut bundle = Vec::new();
+        manager.export_bundle(&mut bundle).unwrap();
+
+        // Import into a different namespace root, simulating a transfer to another machine.
+        let restore_root = temp_root("bundle-import");
+        let mut restore_manager = path_manager(&restore_root);
+        let restored = restore_manager.import_bundle(bundle.as_slice()).unwrap();
+
+        assert_eq!(restored.len(), 2);
+
+        let mut contents: Vec<_> = restored
+            .iter()
+            .map(|managed| std::fs::read(&**managed).unwrap())
+            .collect();
+        contents.sort();
+
+        let mut expected = vec![b"binary data".to_vec(), b"hello world".to_vec()];
+        expected.sort();
+
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn import_bundle_restores_read_only_entries() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = temp_root("bundle-readonly-export");
+        let mut manager = path_manager(&root);
+
+        let asset = manager
+            .relative_to_absolute_path(Path::new("sample.wav"))
+            .unwrap();
+        std::fs::write(&*asset, b"read-only asset").unwrap();
+        std::fs::set_permissions(&*asset, std::fs::Permissions::from_mode(0o444)).unwrap();
+        manager.absolute_to_abstract_path(&asset).unwrap();
+
+        let mut bundle = Vec::new();
+        manager.export_bundle(&mut bundle).unwrap();
+
+        let restore_root = temp_root("bundle-readonly-import");
+        let mut restore_manager = path_manager(&restore_root);
+        let restored = restore_manager.import_bundle(bundle.as_slice()).unwrap();
+
+        assert_eq!(restored.len(), 1);
+        assert_eq!(std::fs::read(&*restored[0]).unwrap(), b"read-only asset");
+        let mode = std::fs::metadata(&*restored[0]).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode, 0o444);
     }
 }